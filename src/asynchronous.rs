@@ -0,0 +1,367 @@
+//! Async counterparts of [`IsoTp`](crate::IsoTp) and [`Uds`](crate::Uds), for callers that want
+//! to multiplex several ECUs or integrate with a runtime such as tokio instead of blocking a
+//! thread per request.
+
+use crate::{NegativeResponseCode, DTC};
+use crate::{Error, UDS_NRES_RCRRP, UDS_RES_NEGATIVE};
+use async_trait::async_trait;
+use std::convert::TryInto;
+use std::time::Duration;
+
+use crate::{
+    UDS_REQ_READBYID, UDS_REQ_READMEM, UDS_REQ_REQUESTDOWNLOAD, UDS_REQ_SECURITY,
+    UDS_REQ_SESSION, UDS_REQ_TESTERPRESENT, UDS_REQ_TRANSFERDATA, UDS_REQ_TRANSFEREXIT,
+    UDS_SUPPRESS_POSITIVE_RESPONSE,
+};
+
+/// Async counterpart of [`IsoTp`](crate::IsoTp).
+#[async_trait]
+pub trait AsyncIsoTp {
+    /// Sends an ISO-TP packet.
+    ///
+    /// # Arguments
+    /// - `id` - the CAN arbitration ID.
+    /// - `data` - The packet payload. Must not be larger than 4095 bytes.
+    async fn send_isotp(&mut self, id: u32, data: &[u8]) -> Result<(), Error>;
+
+    /// Receives an ISO-TP packet.
+    ///
+    /// # Arguments
+    /// - `id` - the CAN arbitration ID to listen for.
+    async fn read_isotp(&mut self, id: u32) -> Result<Vec<u8>, Error>;
+
+    /// Sends an ISO-TP packet and waits for a response
+    async fn query_isotp(&mut self, id: u32, data: &[u8]) -> Result<Vec<u8>, Error> {
+        self.send_isotp(id, data).await?;
+        self.read_isotp(id + 8).await
+    }
+}
+
+/// Async counterpart of [`Uds`](crate::Uds). The blanket implementation for any
+/// [`AsyncIsoTp`] drives the same requestCorrectlyReceivedResponsePending retry and SID
+/// validation logic as [`Uds::query_uds`](crate::Uds::query_uds), with `timeout` bounding each
+/// `query_uds` call as a whole, responsePending retries included, instead of the J2534
+/// millisecond timeout argument used by the blocking transport.
+#[async_trait]
+pub trait AsyncUds: AsyncIsoTp + Send {
+    /// The maximum time to wait for a UDS request to complete, including any
+    /// responsePending retries.
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    /// Sends a UDS message and waits for a response.
+    /// # Arguments
+    /// * `arbitration_id` - the CAN arbitration ID to use when sending. This is incremented by 8 to calculate the expected response ID.
+    /// * `request_sid` - the requested service ID.
+    /// * `data` - the message data.
+    async fn query_uds(
+        &mut self,
+        arbitration_id: u32,
+        request_sid: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+
+    /// Sends a query for a VIN (vehicle identification number).
+    async fn query_vin(&mut self, arbitration_id: u32) -> Result<String, Error> {
+        let data = self.query_uds(arbitration_id, 0x9, &[0x2]).await?;
+        match data.first() {
+            Some(pid) if *pid == 0x2 => (),
+            _ => return Err(Error::InvalidResponsePid),
+        }
+
+        if let Some(pad) = data.iter().skip(1).position(|i| *i != 0 && *i != 1) {
+            Ok(String::from_utf8_lossy(&data[pad + 1..]).to_string())
+        } else {
+            Ok(String::new())
+        }
+    }
+
+    /// Queries the list of diagnostic trouble codes
+    async fn query_trouble_codes(&mut self, arbitration_id: u32) -> Result<Vec<DTC>, Error> {
+        let response = self.query_uds(arbitration_id, 0x03, &[]).await?;
+        if let Some(_size) = response.first() {
+            Ok((&response[1..])
+                .chunks(2)
+                .filter_map(|c| c.try_into().ok())
+                .map(|c| DTC(c))
+                .collect())
+        } else {
+            Err(Error::EmptyResponse)
+        }
+    }
+
+    /// Sets the diagnostic session type
+    async fn set_diagnostic_session(&mut self, arbitration_id: u32, id: u8) -> Result<(), Error> {
+        let response = self.query_uds(arbitration_id, UDS_REQ_SESSION, &[id]).await?;
+        if let Some(&res_id) = response.first() {
+            if res_id == id {
+                Ok(())
+            } else {
+                Err(Error::InvalidSessionType)
+            }
+        } else {
+            Err(Error::EmptyResponse)
+        }
+    }
+
+    /// Requests a security access seed
+    async fn request_security_seed(&mut self, arbitration_id: u32) -> Result<Vec<u8>, Error> {
+        let mut response = self.query_uds(arbitration_id, UDS_REQ_SECURITY, &[1]).await?;
+        if let Some(&access_type) = response.first() {
+            if access_type != 1 {
+                Err(Error::InvalidAccessType)
+            } else {
+                response.remove(0);
+                Ok(response)
+            }
+        } else {
+            Err(Error::EmptyResponse)
+        }
+    }
+
+    /// Authenticates with a security access key. Usually, this is generated
+    /// using the seed retrieved from [`request_security_seed`](AsyncUds::request_security_seed).
+    async fn request_security_key(&mut self, arbitration_id: u32, key: &[u8]) -> Result<(), Error> {
+        let mut request = Vec::with_capacity(key.len() + 1);
+        request.push(2);
+        request.extend_from_slice(key);
+
+        let response = self.query_uds(arbitration_id, UDS_REQ_SECURITY, &request).await?;
+        if response.is_empty() {
+            Err(Error::EmptyResponse)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Requests memory at specified address. Usually, this requires an
+    /// authentication procedure with [`request_security_key`](AsyncUds::request_security_key).
+    async fn read_memory_address(
+        &mut self,
+        arbitration_id: u32,
+        address: u32,
+        length: u16,
+    ) -> Result<Vec<u8>, Error> {
+        let mut req = [0; 6];
+        req[0] = ((address & 0xFF000000) >> 24) as u8;
+        req[1] = ((address & 0xFF0000) >> 16) as u8;
+        req[2] = ((address & 0xFF00) >> 8) as u8;
+        req[3] = (address & 0xFF) as u8;
+
+        req[4] = (length >> 8) as u8;
+        req[5] = (length & 0xFF) as u8;
+
+        let response = self.query_uds(arbitration_id, UDS_REQ_READMEM, &req).await?;
+        Ok(response)
+    }
+
+    /// Reads a data record by its data identifier (DID).
+    async fn read_data_by_identifier(
+        &mut self,
+        arbitration_id: u32,
+        did: u16,
+    ) -> Result<Vec<u8>, Error> {
+        let req = [(did >> 8) as u8, (did & 0xFF) as u8];
+        let mut response = self.query_uds(arbitration_id, UDS_REQ_READBYID, &req).await?;
+        if response.len() < 2 {
+            return Err(Error::EmptyResponse);
+        }
+
+        let echoed_did = u16::from_be_bytes([response[0], response[1]]);
+        if echoed_did != did {
+            return Err(Error::InvalidResponsePid);
+        }
+
+        response.drain(..2);
+        Ok(response)
+    }
+
+    /// Queries the common firmware identification DIDs (VIN, part number,
+    /// software/hardware versions in the `0xF180`-`0xF195` range) and
+    /// returns the ones the ECU supports as `(did, value)` pairs of ASCII strings.
+    async fn query_firmware_versions(
+        &mut self,
+        arbitration_id: u32,
+    ) -> Result<Vec<(u16, String)>, Error> {
+        let mut versions = Vec::new();
+        for did in crate::UDS_FIRMWARE_DIDS {
+            if let Ok(data) = self.read_data_by_identifier(arbitration_id, did).await {
+                versions.push((did, String::from_utf8_lossy(&data).trim().to_string()));
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Requests a memory download to the ECU (service `0x34`). See
+    /// [`Uds::request_download`](crate::Uds::request_download) for the argument layout.
+    async fn request_download(
+        &mut self,
+        arbitration_id: u32,
+        address: u32,
+        size: u32,
+        data_format: u8,
+        address_length_format: u8,
+    ) -> Result<usize, Error> {
+        let address_len = (address_length_format & 0x0F) as usize;
+        let size_len = ((address_length_format >> 4) & 0x0F) as usize;
+
+        let mut req = Vec::with_capacity(2 + address_len + size_len);
+        req.push(data_format);
+        req.push(address_length_format);
+        req.extend_from_slice(&address.to_be_bytes()[4 - address_len..]);
+        req.extend_from_slice(&size.to_be_bytes()[4 - size_len..]);
+
+        let response = self
+            .query_uds(arbitration_id, UDS_REQ_REQUESTDOWNLOAD, &req)
+            .await?;
+        let length_format = match response.first() {
+            Some(&b) => b,
+            None => return Err(Error::EmptyResponse),
+        };
+
+        let length_size = (length_format >> 4) as usize;
+        let length_bytes = response.get(1..1 + length_size).ok_or(Error::EmptyResponse)?;
+        let mut max_block_length = 0usize;
+        for &b in length_bytes {
+            max_block_length = (max_block_length << 8) | b as usize;
+        }
+        Ok(max_block_length)
+    }
+
+    /// Sends a block of firmware data (service `0x36`). See
+    /// [`Uds::transfer_data`](crate::Uds::transfer_data).
+    async fn transfer_data(
+        &mut self,
+        arbitration_id: u32,
+        block_sequence_counter: u8,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let mut req = Vec::with_capacity(data.len() + 1);
+        req.push(block_sequence_counter);
+        req.extend_from_slice(data);
+
+        let response = self.query_uds(arbitration_id, UDS_REQ_TRANSFERDATA, &req).await?;
+        match response.first() {
+            Some(&counter) if counter == block_sequence_counter => Ok(()),
+            Some(&counter) => Err(Error::InvalidBlockSequenceCounter(counter)),
+            None => Err(Error::EmptyResponse),
+        }
+    }
+
+    /// Ends a download/upload sequence started by [`request_download`](AsyncUds::request_download)
+    /// (service `0x37`).
+    async fn request_transfer_exit(&mut self, arbitration_id: u32) -> Result<(), Error> {
+        self.query_uds(arbitration_id, UDS_REQ_TRANSFEREXIT, &[]).await?;
+        Ok(())
+    }
+
+    /// Drives a full `RequestDownload` / `TransferData` / `RequestTransferExit` sequence,
+    /// chunking `data` according to the maximum block length negotiated with the ECU.
+    async fn download_firmware(
+        &mut self,
+        arbitration_id: u32,
+        address: u32,
+        data: &[u8],
+        data_format: u8,
+        address_length_format: u8,
+    ) -> Result<(), Error> {
+        let max_block_length = self
+            .request_download(
+                arbitration_id,
+                address,
+                data.len() as u32,
+                data_format,
+                address_length_format,
+            )
+            .await?;
+
+        // maxNumberOfBlockLength counts the whole TransferData message, including the
+        // service ID and the block sequence counter.
+        let chunk_size = max_block_length.saturating_sub(2).max(1);
+        let mut counter: u8 = 1;
+        for chunk in data.chunks(chunk_size) {
+            self.transfer_data(arbitration_id, counter, chunk).await?;
+            counter = counter.wrapping_add(1);
+        }
+
+        self.request_transfer_exit(arbitration_id).await
+    }
+
+    /// Sends a TesterPresent request (service `0x3E`, sub-function `0x00`) to keep a
+    /// non-default diagnostic session from timing out.
+    async fn tester_present(
+        &mut self,
+        arbitration_id: u32,
+        suppress_response: bool,
+    ) -> Result<(), Error> {
+        let sub_function = if suppress_response {
+            UDS_SUPPRESS_POSITIVE_RESPONSE
+        } else {
+            0x00
+        };
+
+        if suppress_response {
+            self.send_isotp(arbitration_id, &[UDS_REQ_TESTERPRESENT, sub_function])
+                .await
+        } else {
+            self.query_uds(arbitration_id, UDS_REQ_TESTERPRESENT, &[sub_function])
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl<I: AsyncIsoTp + Send> AsyncUds for I {
+    async fn query_uds(
+        &mut self,
+        arbitration_id: u32,
+        request_sid: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        // Build the request
+        let mut request = Vec::with_capacity(data.len() + 1);
+        request.push(request_sid);
+        request.extend_from_slice(data);
+
+        // A single deadline bounds the whole responsePending retry loop below, rather than
+        // being reset on every retry, so a stuck ECU can't keep this waiting forever.
+        let timeout = self.timeout();
+        let attempt = async {
+            loop {
+                let mut response = self.query_isotp(arbitration_id, &request).await?;
+
+                let response_sid = match response.first() {
+                    Some(sid) => *sid,
+                    None => return Err(Error::EmptyResponse),
+                };
+
+                // Check the response SID
+                if response_sid == UDS_RES_NEGATIVE {
+                    // Check negative response code
+                    let code = match response.get(2) {
+                        Some(&code) => code,
+                        None => return Err(Error::EmptyResponse),
+                    };
+                    if code == UDS_NRES_RCRRP {
+                        // The transmitter is still processing; continue waiting.
+                        continue;
+                    }
+                    return Err(Error::NegativeResponse(NegativeResponseCode::from(code)));
+                }
+
+                if response_sid != request_sid + 0x40 {
+                    return Err(Error::InvalidResponseSid(response_sid));
+                }
+
+                response.remove(0);
+                return Ok(response);
+            }
+        };
+
+        tokio::time::timeout(timeout, attempt)
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+}
@@ -10,13 +10,18 @@ pub enum Error {
     /// PassThru error. This is available when the 'passthru' feature is enabled
     PassThru(#[from] j2534::Error),
 
+    #[cfg(feature = "socketcan")]
+    #[error(transparent)]
+    /// SocketCAN error. This is available when the 'socketcan' feature is enabled
+    SocketCan(#[from] std::io::Error),
+
     /// An empty UDS response was received
     #[error("empty UDS response")]
     EmptyResponse,
 
     /// A negative UDS response was received
-    #[error("negative response: {0:?}")]
-    NegativeResponse(Option<u8>),
+    #[error("negative response: {0}")]
+    NegativeResponse(NegativeResponseCode),
 
     /// An invalid SID was included in a UDS response
     #[error("invalid response SID {0:X}")]
@@ -33,14 +38,62 @@ pub enum Error {
     /// Invalid security access type
     #[error("invalid security access type in response")]
     InvalidAccessType,
+
+    /// The sender reported a flow control overflow during an ISO-TP transfer
+    #[error("flow control overflow")]
+    FlowControlOverflow,
+
+    /// An unrecognized flow status was received in a flow control frame
+    #[error("invalid flow status {0:X}")]
+    InvalidFlowStatus(u8),
+
+    /// A consecutive frame was received with an unexpected sequence number
+    #[error("invalid consecutive frame sequence number {0:X}")]
+    InvalidSequenceNumber(u8),
+
+    /// A `TransferData` response echoed an unexpected block sequence counter
+    #[error("invalid transfer block sequence counter {0:X}")]
+    InvalidBlockSequenceCounter(u8),
+
+    /// A CAN frame was too short to contain the data its PCI byte promised
+    #[error("truncated ISO-TP frame")]
+    TruncatedFrame,
+
+    /// A Single Frame's PCI length nibble was out of the valid 0-7 range
+    #[error("invalid single frame length {0:X}")]
+    InvalidSingleFrameLength(u8),
+
+    /// The arbitration ID did not fit in a standard or extended CAN ID
+    #[error("invalid arbitration ID {0:X}")]
+    InvalidArbitrationId(u32),
+
+    /// An async UDS request did not complete before its deadline elapsed
+    #[error("UDS request timed out")]
+    Timeout,
 }
 
 /// J2534 support
 #[cfg(feature = "passthru")]
 pub mod passthru;
 
+/// Software ISO-TP, for use with any raw CAN backend
+pub mod software;
+
+/// Linux SocketCAN support
+#[cfg(feature = "socketcan")]
+pub mod socketcan;
+
+/// Async ISO-TP/UDS support, for use with a tokio runtime
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+
 #[cfg(feature = "passthru")]
 pub use passthru::PassThruIsoTp;
+pub use software::{CanInterface, SoftwareIsoTp};
+#[cfg(feature = "socketcan")]
+pub use socketcan::SocketCan;
+#[cfg(feature = "tokio")]
+pub use asynchronous::{AsyncIsoTp, AsyncUds};
 use std::convert::TryInto;
 use std::fmt;
 use std::fmt::{Display, Formatter};
@@ -74,7 +127,15 @@ const UDS_REQ_READMEM: u8 = 0x23;
 const UDS_REQ_REQUESTDOWNLOAD: u8 = 0x34;
 const UDS_REQ_REQUESTUPLOAD: u8 = 0x35;
 const UDS_REQ_TRANSFERDATA: u8 = 0x36;
+const UDS_REQ_TRANSFEREXIT: u8 = 0x37;
 const UDS_REQ_READBYID: u8 = 0x22;
+const UDS_REQ_TESTERPRESENT: u8 = 0x3E;
+
+// suppressPositiveResponse bit, set in the sub-function byte of a request
+const UDS_SUPPRESS_POSITIVE_RESPONSE: u8 = 0x80;
+
+// Common firmware identification DIDs (VIN, part number, software/hardware versions).
+const UDS_FIRMWARE_DIDS: std::ops::RangeInclusive<u16> = 0xF180..=0xF195;
 
 const UDS_RES_NEGATIVE: u8 = 0x7F;
 
@@ -82,6 +143,79 @@ const UDS_RES_NEGATIVE: u8 = 0x7F;
 // requestCorrectlyReceivedResponsePending
 const UDS_NRES_RCRRP: u8 = 0x78;
 
+/// A negative response code (NRC) as defined by ISO 14229.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeResponseCode {
+    /// `0x10` - generalReject
+    GeneralReject,
+    /// `0x11` - serviceNotSupported
+    ServiceNotSupported,
+    /// `0x13` - incorrectMessageLengthOrInvalidFormat
+    IncorrectMessageLengthOrInvalidFormat,
+    /// `0x22` - conditionsNotCorrect
+    ConditionsNotCorrect,
+    /// `0x31` - requestOutOfRange
+    RequestOutOfRange,
+    /// `0x33` - securityAccessDenied
+    SecurityAccessDenied,
+    /// `0x35` - invalidKey
+    InvalidKey,
+    /// `0x36` - exceedNumberOfAttempts
+    ExceedNumberOfAttempts,
+    /// `0x37` - requiredTimeDelayNotExpired
+    RequiredTimeDelayNotExpired,
+    /// `0x78` - requestCorrectlyReceivedResponsePending
+    ResponsePending,
+    /// `0x7F` - serviceNotSupportedInActiveSession
+    ServiceNotSupportedInActiveSession,
+    /// An NRC not recognized by this crate
+    Unknown(u8),
+}
+
+impl From<u8> for NegativeResponseCode {
+    fn from(code: u8) -> Self {
+        match code {
+            0x10 => NegativeResponseCode::GeneralReject,
+            0x11 => NegativeResponseCode::ServiceNotSupported,
+            0x13 => NegativeResponseCode::IncorrectMessageLengthOrInvalidFormat,
+            0x22 => NegativeResponseCode::ConditionsNotCorrect,
+            0x31 => NegativeResponseCode::RequestOutOfRange,
+            0x33 => NegativeResponseCode::SecurityAccessDenied,
+            0x35 => NegativeResponseCode::InvalidKey,
+            0x36 => NegativeResponseCode::ExceedNumberOfAttempts,
+            0x37 => NegativeResponseCode::RequiredTimeDelayNotExpired,
+            UDS_NRES_RCRRP => NegativeResponseCode::ResponsePending,
+            0x7F => NegativeResponseCode::ServiceNotSupportedInActiveSession,
+            code => NegativeResponseCode::Unknown(code),
+        }
+    }
+}
+
+impl Display for NegativeResponseCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NegativeResponseCode::GeneralReject => write!(f, "generalReject"),
+            NegativeResponseCode::ServiceNotSupported => write!(f, "serviceNotSupported"),
+            NegativeResponseCode::IncorrectMessageLengthOrInvalidFormat => {
+                write!(f, "incorrectMessageLengthOrInvalidFormat")
+            }
+            NegativeResponseCode::ConditionsNotCorrect => write!(f, "conditionsNotCorrect"),
+            NegativeResponseCode::RequestOutOfRange => write!(f, "requestOutOfRange"),
+            NegativeResponseCode::SecurityAccessDenied => write!(f, "securityAccessDenied"),
+            NegativeResponseCode::InvalidKey => write!(f, "invalidKey"),
+            NegativeResponseCode::ExceedNumberOfAttempts => write!(f, "exceedNumberOfAttempts"),
+            NegativeResponseCode::RequiredTimeDelayNotExpired => {
+                write!(f, "requiredTimeDelayNotExpired")
+            }
+            NegativeResponseCode::ResponsePending => write!(f, "responsePending"),
+            NegativeResponseCode::ServiceNotSupportedInActiveSession => {
+                write!(f, "serviceNotSupportedInActiveSession")
+            }
+            NegativeResponseCode::Unknown(code) => write!(f, "unknown ({:#04X})", code),
+        }
+    }
+}
+
 /// Diagnostic trouble code
 pub struct DTC([u8; 2]);
 
@@ -106,7 +240,7 @@ impl Display for DTC {
 
 /// Unified diagnostic services. This is the standard protocol
 /// used for reading PIDs and communicating with ECUs.
-pub trait Uds {
+pub trait Uds: IsoTp {
     /// Sends a UDS message and waits for a response.
     /// # Arguments
     /// * `arbitration_id` - the CAN arbitration ID to use when sending. This is incremented by 8 to calculate the expected response ID.
@@ -212,6 +346,214 @@ pub trait Uds {
         let response = self.query_uds(arbitration_id, UDS_REQ_READMEM, &req)?;
         Ok(response)
     }
+
+    /// Reads a data record by its data identifier (DID).
+    fn read_data_by_identifier(
+        &mut self,
+        arbitration_id: u32,
+        did: u16,
+    ) -> Result<Vec<u8>, Error> {
+        let req = [(did >> 8) as u8, (did & 0xFF) as u8];
+        let mut response = self.query_uds(arbitration_id, UDS_REQ_READBYID, &req)?;
+        if response.len() < 2 {
+            return Err(Error::EmptyResponse);
+        }
+
+        let echoed_did = u16::from_be_bytes([response[0], response[1]]);
+        if echoed_did != did {
+            return Err(Error::InvalidResponsePid);
+        }
+
+        response.drain(..2);
+        Ok(response)
+    }
+
+    /// Queries the common firmware identification DIDs (VIN, part number,
+    /// software/hardware versions in the `0xF180`-`0xF195` range) and
+    /// returns the ones the ECU supports as `(did, value)` pairs of ASCII strings.
+    fn query_firmware_versions(&mut self, arbitration_id: u32) -> Result<Vec<(u16, String)>, Error> {
+        let mut versions = Vec::new();
+        for did in UDS_FIRMWARE_DIDS {
+            if let Ok(data) = self.read_data_by_identifier(arbitration_id, did) {
+                versions.push((did, String::from_utf8_lossy(&data).trim().to_string()));
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Requests a memory download to the ECU (service `0x34`), e.g. to flash
+    /// new firmware. Returns the maximum number of bytes the ECU will accept
+    /// per [`transfer_data`](Uds::transfer_data) block.
+    ///
+    /// # Arguments
+    /// * `address` / `size` - the target memory address and the number of bytes to transfer.
+    /// * `data_format` - the `dataFormatIdentifier`, e.g. `0x00` for raw unencrypted/uncompressed data.
+    /// * `address_length_format` - the `addressAndLengthFormatIdentifier`; its low nibble gives the
+    ///   number of bytes used to encode `address` and its high nibble the number of bytes used to
+    ///   encode `size`.
+    fn request_download(
+        &mut self,
+        arbitration_id: u32,
+        address: u32,
+        size: u32,
+        data_format: u8,
+        address_length_format: u8,
+    ) -> Result<usize, Error> {
+        let address_len = (address_length_format & 0x0F) as usize;
+        let size_len = ((address_length_format >> 4) & 0x0F) as usize;
+
+        let mut req = Vec::with_capacity(2 + address_len + size_len);
+        req.push(data_format);
+        req.push(address_length_format);
+        req.extend_from_slice(&address.to_be_bytes()[4 - address_len..]);
+        req.extend_from_slice(&size.to_be_bytes()[4 - size_len..]);
+
+        let response = self.query_uds(arbitration_id, UDS_REQ_REQUESTDOWNLOAD, &req)?;
+        let length_format = match response.first() {
+            Some(&b) => b,
+            None => return Err(Error::EmptyResponse),
+        };
+
+        let length_size = (length_format >> 4) as usize;
+        let length_bytes = response.get(1..1 + length_size).ok_or(Error::EmptyResponse)?;
+        let mut max_block_length = 0usize;
+        for &b in length_bytes {
+            max_block_length = (max_block_length << 8) | b as usize;
+        }
+        Ok(max_block_length)
+    }
+
+    /// Sends a block of firmware data (service `0x36`), started by
+    /// [`request_download`](Uds::request_download). `block_sequence_counter` starts at `1` and
+    /// wraps from `0xFF` back to `0x00`.
+    fn transfer_data(
+        &mut self,
+        arbitration_id: u32,
+        block_sequence_counter: u8,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let mut req = Vec::with_capacity(data.len() + 1);
+        req.push(block_sequence_counter);
+        req.extend_from_slice(data);
+
+        let response = self.query_uds(arbitration_id, UDS_REQ_TRANSFERDATA, &req)?;
+        match response.first() {
+            Some(&counter) if counter == block_sequence_counter => Ok(()),
+            Some(&counter) => Err(Error::InvalidBlockSequenceCounter(counter)),
+            None => Err(Error::EmptyResponse),
+        }
+    }
+
+    /// Ends a download/upload sequence started by [`request_download`](Uds::request_download)
+    /// (service `0x37`).
+    fn request_transfer_exit(&mut self, arbitration_id: u32) -> Result<(), Error> {
+        self.query_uds(arbitration_id, UDS_REQ_TRANSFEREXIT, &[])?;
+        Ok(())
+    }
+
+    /// Drives a full `RequestDownload` / `TransferData` / `RequestTransferExit` sequence,
+    /// chunking `data` according to the maximum block length negotiated with the ECU.
+    fn download_firmware(
+        &mut self,
+        arbitration_id: u32,
+        address: u32,
+        data: &[u8],
+        data_format: u8,
+        address_length_format: u8,
+    ) -> Result<(), Error> {
+        let max_block_length = self.request_download(
+            arbitration_id,
+            address,
+            data.len() as u32,
+            data_format,
+            address_length_format,
+        )?;
+
+        // maxNumberOfBlockLength counts the whole TransferData message, including the
+        // service ID and the block sequence counter.
+        let chunk_size = max_block_length.saturating_sub(2).max(1);
+        let mut counter: u8 = 1;
+        for chunk in data.chunks(chunk_size) {
+            self.transfer_data(arbitration_id, counter, chunk)?;
+            counter = counter.wrapping_add(1);
+        }
+
+        self.request_transfer_exit(arbitration_id)
+    }
+
+    /// Sends a TesterPresent request (service `0x3E`, sub-function `0x00`) to keep a
+    /// non-default diagnostic session, e.g. one entered with
+    /// [`set_diagnostic_session`](Uds::set_diagnostic_session), from timing out.
+    ///
+    /// If `suppress_response` is set, the ECU is asked not to send a positive response and
+    /// the request is fired without waiting for one.
+    fn tester_present(&mut self, arbitration_id: u32, suppress_response: bool) -> Result<(), Error> {
+        let sub_function = if suppress_response {
+            UDS_SUPPRESS_POSITIVE_RESPONSE
+        } else {
+            0x00
+        };
+
+        if suppress_response {
+            self.send_isotp(arbitration_id, &[UDS_REQ_TESTERPRESENT, sub_function])
+        } else {
+            self.query_uds(arbitration_id, UDS_REQ_TESTERPRESENT, &[sub_function])?;
+            Ok(())
+        }
+    }
+}
+
+/// Periodically sends [`Uds::tester_present`] keepalives on a background thread, holding a
+/// non-default diagnostic session open while the caller performs a long-running sequence such
+/// as security access authentication or a [`read_memory_address`](Uds::read_memory_address) dump
+/// on the same transport.
+///
+/// The transport is shared with the caller via `Arc<Mutex<T>>` rather than being moved into the
+/// keepalive thread, so it stays available for foreground requests between keepalive pings.
+/// The keepalive thread is stopped when this handle is dropped.
+pub struct TesterPresentKeepAlive {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl TesterPresentKeepAlive {
+    /// Spawns a thread that sends a suppressed-response TesterPresent request to
+    /// `arbitration_id` every `interval`, until the returned handle is dropped. `uds` is shared
+    /// with the caller, who can keep using it for foreground requests in between pings.
+    pub fn spawn<T>(
+        uds: std::sync::Arc<std::sync::Mutex<T>>,
+        arbitration_id: u32,
+        interval: std::time::Duration,
+    ) -> TesterPresentKeepAlive
+    where
+        T: Uds + Send + 'static,
+    {
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = std::thread::spawn(move || {
+            while !thread_stop.load(std::sync::atomic::Ordering::Relaxed) {
+                {
+                    let mut uds = uds.lock().expect("tester present keepalive: poisoned lock");
+                    let _ = uds.tester_present(arbitration_id, true);
+                }
+                std::thread::sleep(interval);
+            }
+        });
+
+        TesterPresentKeepAlive {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for TesterPresentKeepAlive {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
 }
 
 impl<I: IsoTp> Uds for I {
@@ -237,12 +579,15 @@ impl<I: IsoTp> Uds for I {
             // Check the response SID
             if response_sid == UDS_RES_NEGATIVE {
                 // Check negative response code
-                let code = response.get(1).map(|c| *c);
-                if code == Some(UDS_NRES_RCRRP) {
+                let code = match response.get(2) {
+                    Some(&code) => code,
+                    None => return Err(Error::EmptyResponse),
+                };
+                if code == UDS_NRES_RCRRP {
                     // The transmitter is still processing; continue waiting.
                     continue;
                 }
-                return Err(Error::NegativeResponse(response.get(2).map(|c| *c)));
+                return Err(Error::NegativeResponse(NegativeResponseCode::from(code)));
             }
 
             if response_sid != request_sid + 0x40 {
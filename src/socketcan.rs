@@ -0,0 +1,50 @@
+//! Linux SocketCAN support
+
+use crate::{CanInterface, Error};
+use socketcan::{CanFrame, ExtendedId, Frame, Id, Socket, StandardId};
+
+/// A raw CAN interface backed by a Linux SocketCAN socket, for use with
+/// [`SoftwareIsoTp`](crate::SoftwareIsoTp) on systems without a J2534 adapter.
+pub struct SocketCan {
+    socket: socketcan::CanSocket,
+}
+
+impl SocketCan {
+    /// Opens a SocketCAN interface by name, e.g. `can0`.
+    pub fn open(interface: &str) -> Result<SocketCan, Error> {
+        let socket = socketcan::CanSocket::open(interface)?;
+        Ok(SocketCan { socket })
+    }
+}
+
+fn arbitration_id(id: u32) -> Result<Id, Error> {
+    match StandardId::new(id as u16) {
+        Some(sid) if id <= 0x7FF => Ok(Id::Standard(sid)),
+        _ => ExtendedId::new(id)
+            .map(Id::Extended)
+            .ok_or(Error::InvalidArbitrationId(id)),
+    }
+}
+
+impl CanInterface for SocketCan {
+    fn send_frame(&mut self, id: u32, data: &[u8]) -> Result<(), Error> {
+        let frame = CanFrame::new(arbitration_id(id)?, data).ok_or_else(|| {
+            Error::SocketCan(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "CAN frame data exceeds 8 bytes",
+            ))
+        })?;
+        self.socket.write_frame(&frame)?;
+        Ok(())
+    }
+
+    fn recv_frame(&mut self) -> Result<(u32, Vec<u8>), Error> {
+        loop {
+            match self.socket.read_frame()? {
+                CanFrame::Data(frame) => return Ok((frame.raw_id(), frame.data().to_vec())),
+                // Remote and error frames carry no ISO-TP payload.
+                CanFrame::Remote(_) | CanFrame::Error(_) => continue,
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Software ISO-TP (ISO 15765-2) implementation for backends that only
+//! expose raw CAN frame send/receive, without hardware-assisted segmentation.
+
+use crate::{Error, IsoTp};
+use std::time::Duration;
+
+/// The number of data bytes in a CAN frame used for ISO-TP, including the PCI byte.
+const ISOTP_FRAME_LEN: usize = 8;
+
+// Protocol control information (PCI) nibbles, found in the high nibble of byte 0.
+const PCI_SINGLE_FRAME: u8 = 0x0;
+const PCI_FIRST_FRAME: u8 = 0x1;
+const PCI_CONSECUTIVE_FRAME: u8 = 0x2;
+const PCI_FLOW_CONTROL: u8 = 0x3;
+
+// Flow control status, found in the low nibble of byte 0 of a flow control frame.
+const FC_CONTINUE_TO_SEND: u8 = 0;
+const FC_WAIT: u8 = 1;
+const FC_OVERFLOW: u8 = 2;
+
+/// A raw CAN interface capable of sending and receiving single frames.
+///
+/// Implementations are expected to pass frames through unmodified; any
+/// required padding to [`ISOTP_FRAME_LEN`](crate) bytes is handled by
+/// [`SoftwareIsoTp`] itself.
+pub trait CanInterface {
+    /// Sends a single CAN frame with the given arbitration ID.
+    fn send_frame(&mut self, id: u32, data: &[u8]) -> Result<(), Error>;
+
+    /// Blocks until a CAN frame is received, returning its arbitration ID and payload.
+    fn recv_frame(&mut self) -> Result<(u32, Vec<u8>), Error>;
+}
+
+/// ISO-TP implementation that performs ISO 15765-2 segmentation and
+/// reassembly in software on top of any [`CanInterface`].
+///
+/// This allows the [`Uds`](crate::Uds) trait to be used on plain CAN
+/// adapters that do not implement ISO-TP in hardware, unlike
+/// [`PassThruIsoTp`](crate::PassThruIsoTp).
+pub struct SoftwareIsoTp<C> {
+    can: C,
+}
+
+impl<C: CanInterface> SoftwareIsoTp<C> {
+    /// Wraps a raw CAN interface with software ISO-TP segmentation.
+    pub fn new(can: C) -> SoftwareIsoTp<C> {
+        SoftwareIsoTp { can }
+    }
+
+    fn recv_flow_control(&mut self, id: u32) -> Result<(u8, u8), Error> {
+        loop {
+            let (frame_id, frame) = self.can.recv_frame()?;
+            if frame_id != id || frame.is_empty() {
+                continue;
+            }
+            if frame[0] >> 4 != PCI_FLOW_CONTROL {
+                continue;
+            }
+            if frame.len() < 3 {
+                return Err(Error::TruncatedFrame);
+            }
+
+            match frame[0] & 0x0F {
+                FC_CONTINUE_TO_SEND => return Ok((frame[1], frame[2])),
+                FC_WAIT => continue,
+                FC_OVERFLOW => return Err(Error::FlowControlOverflow),
+                status => return Err(Error::InvalidFlowStatus(status)),
+            }
+        }
+    }
+}
+
+/// Sleeps for the separation time indicated by an STmin byte.
+///
+/// `0x00`-`0x7F` are whole milliseconds; `0xF1`-`0xF9` are 100-900 microseconds.
+fn sleep_st_min(st_min: u8) {
+    let delay = match st_min {
+        0x00..=0x7F => Duration::from_millis(st_min as u64),
+        0xF1..=0xF9 => Duration::from_micros((st_min - 0xF0) as u64 * 100),
+        _ => Duration::from_millis(0),
+    };
+    if !delay.is_zero() {
+        std::thread::sleep(delay);
+    }
+}
+
+impl<C: CanInterface> IsoTp for SoftwareIsoTp<C> {
+    fn send_isotp(&mut self, id: u32, data: &[u8]) -> Result<(), Error> {
+        if data.len() <= 7 {
+            let mut frame = [0u8; ISOTP_FRAME_LEN];
+            frame[0] = (PCI_SINGLE_FRAME << 4) | data.len() as u8;
+            frame[1..1 + data.len()].copy_from_slice(data);
+            return self.can.send_frame(id, &frame);
+        }
+
+        let len = data.len();
+        let mut frame = [0u8; ISOTP_FRAME_LEN];
+        frame[0] = (PCI_FIRST_FRAME << 4) | ((len >> 8) & 0x0F) as u8;
+        frame[1] = (len & 0xFF) as u8;
+        frame[2..8].copy_from_slice(&data[..6]);
+        self.can.send_frame(id, &frame)?;
+
+        let mut sent = 6;
+        let mut sequence: u8 = 1;
+        while sent < len {
+            let (block_size, st_min) = self.recv_flow_control(id + 8)?;
+            let block_size = if block_size == 0 { u32::MAX } else { block_size as u32 };
+
+            let mut sent_in_block = 0;
+            while sent < len && sent_in_block < block_size {
+                let chunk_len = (len - sent).min(7);
+                let mut cf = [0u8; ISOTP_FRAME_LEN];
+                cf[0] = (PCI_CONSECUTIVE_FRAME << 4) | sequence;
+                cf[1..1 + chunk_len].copy_from_slice(&data[sent..sent + chunk_len]);
+                self.can.send_frame(id, &cf)?;
+
+                sent += chunk_len;
+                sequence = (sequence + 1) & 0x0F;
+                sent_in_block += 1;
+
+                if sent < len && sent_in_block < block_size {
+                    sleep_st_min(st_min);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_isotp(&mut self, id: u32) -> Result<Vec<u8>, Error> {
+        loop {
+            let (frame_id, frame) = self.can.recv_frame()?;
+            if frame_id != id || frame.is_empty() {
+                continue;
+            }
+
+            match frame[0] >> 4 {
+                PCI_SINGLE_FRAME => {
+                    let len = (frame[0] & 0x0F) as usize;
+                    if len > 7 {
+                        return Err(Error::InvalidSingleFrameLength(frame[0] & 0x0F));
+                    }
+                    if frame.len() < 1 + len {
+                        return Err(Error::TruncatedFrame);
+                    }
+                    return Ok(frame[1..1 + len].to_vec());
+                }
+                PCI_FIRST_FRAME => {
+                    if frame.len() < ISOTP_FRAME_LEN {
+                        return Err(Error::TruncatedFrame);
+                    }
+                    let len = (((frame[0] & 0x0F) as usize) << 8) | frame[1] as usize;
+                    let mut data = Vec::with_capacity(len);
+                    data.extend_from_slice(&frame[2..8.min(2 + len)]);
+
+                    // Tell the sender to continue without restriction.
+                    let fc = [(PCI_FLOW_CONTROL << 4) | FC_CONTINUE_TO_SEND, 0, 0, 0, 0, 0, 0, 0];
+                    self.can.send_frame(id - 8, &fc)?;
+
+                    let mut sequence: u8 = 1;
+                    while data.len() < len {
+                        let (cf_id, cf) = self.can.recv_frame()?;
+                        if cf_id != id || cf.is_empty() {
+                            continue;
+                        }
+                        if cf[0] >> 4 != PCI_CONSECUTIVE_FRAME {
+                            continue;
+                        }
+
+                        let seq = cf[0] & 0x0F;
+                        if seq != sequence {
+                            return Err(Error::InvalidSequenceNumber(seq));
+                        }
+
+                        let remaining = (len - data.len()).min(7);
+                        if cf.len() < 1 + remaining {
+                            return Err(Error::TruncatedFrame);
+                        }
+                        data.extend_from_slice(&cf[1..1 + remaining]);
+                        sequence = (sequence + 1) & 0x0F;
+                    }
+                    return Ok(data);
+                }
+                _ => continue,
+            }
+        }
+    }
+}